@@ -1,21 +1,65 @@
+// `#![cfg_attr(not(feature = "std"), no_std)]` lives on the crate root,
+// which isn't part of this tree; everything below only assumes `core` plus
+// the `alloc` feature, so it compiles either way.
 use crate::pri::{compose_pri, SyslogFacility, SyslogSeverity};
 use crate::structured_data;
-use chrono::prelude::*;
-use std::fmt;
+use crate::timestamp::Timestamp;
+use core::fmt;
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "alloc")]
+use alloc::string::{String, ToString};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+// `pri.rs` and `structured_data.rs` aren't part of this tree (it only
+// carries the files this series touches), so `SyslogFacility`,
+// `SyslogSeverity`, and `StructuredElement` can't be given matching
+// `#[cfg_attr(feature = "serde", ...)]` derives here. `Message`'s own
+// derive below requires all three to implement `Serialize`/`Deserialize`
+// wherever they're defined — enabling the `serde` feature isn't safe to
+// ship until they do.
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Protocol {
     RFC3164,
     RFC5424(u32),
 }
 
+/// The default `Timestamp` backend, used when `T` isn't specified
+/// explicitly. Requires the (default-enabled) `timestamp-chrono` feature.
+#[cfg(feature = "timestamp-chrono")]
+pub type DefaultTimestamp = chrono::DateTime<chrono::FixedOffset>;
+
+// `structured_data` is a `Vec`, so `Message` itself needs `alloc` to exist
+// at all — the `alloc` gates on the impls below aren't cosmetic, they
+// mirror the fact that the type they're implemented for isn't defined
+// without this feature either.
+#[cfg(feature = "alloc")]
 #[derive(Clone, Debug)]
-pub struct Message<S: AsRef<str> + Ord + PartialEq + Clone> {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "S: serde::Serialize",
+        deserialize = "S: serde::Deserialize<'de>"
+    ))
+)]
+pub struct Message<S: AsRef<str> + Ord + PartialEq + Clone, T: Timestamp = DefaultTimestamp> {
     pub protocol: Protocol,
     pub facility: Option<SyslogFacility>,
     pub severity: Option<SyslogSeverity>,
-    pub timestamp: Option<DateTime<FixedOffset>>,
+    // `serde_timestamp::rfc3339::option` is generic over `T: Timestamp`
+    // (it goes through `T::to_rfc3339`/`T::parse_rfc3339` rather than a
+    // concrete `chrono` type), so this works whichever `Timestamp` backend
+    // `T` is instantiated with.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_timestamp::rfc3339::option")
+    )]
+    pub timestamp: Option<T>,
     pub hostname: Option<S>,
     pub appname: Option<S>,
     pub procid: Option<S>,
@@ -24,55 +68,106 @@ pub struct Message<S: AsRef<str> + Ord + PartialEq + Clone> {
     pub msg: S,
 }
 
-impl<S: AsRef<str> + Ord + PartialEq + Clone> fmt::Display for Message<S> {
+// `Display` renders each protocol in its own wire format rather than
+// reusing RFC 5424's layout for both: RFC 3164 has no version, NILVALUE, or
+// structured data, and its timestamp isn't RFC 3339. Fields that are absent
+// are written as `-` only where the RFC actually defines that NILVALUE
+// (RFC 5424); RFC 3164 has no such convention, so absent fields are left
+// out entirely.
+//
+// This round-trips for RFC 5424 (every field has an explicit NILVALUE
+// slot, so presence/absence is never ambiguous on the wire) and for RFC
+// 3164 messages whose hostname and appname are either both present or
+// both absent. It does *not* round-trip an RFC 3164 message with exactly
+// one of `hostname`/`appname` set: `fmt_rfc3164_header` then emits a
+// single bare token before the `:`, and `rfc3164::header`'s hostname and
+// appname grammars aren't disjoint, so re-parsing that token can assign it
+// to the other field (the same ambiguity `rfc3164`'s own
+// `parse_3164_header_timestamp` test already calls out for parsing raw
+// input). Fixing that needs the hostname/appname grammars themselves to
+// disambiguate, which is out of scope for `Display`.
+#[cfg(feature = "alloc")]
+impl<S: AsRef<str> + Ord + PartialEq + Clone, T: Timestamp> fmt::Display for Message<S, T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let empty = "-".to_string();
-        
         write!(
             f,
-            "<{}>{} {} {} {} {} {} ",
+            "<{}>",
             compose_pri(
                 self.facility.unwrap_or(SyslogFacility::LOG_SYSLOG),
                 self.severity.unwrap_or(SyslogSeverity::SEV_DEBUG)
-            ),
-            match self.protocol {
-                Protocol::RFC3164 => "".to_string(),
-                Protocol::RFC5424(version) => version.to_string()
-            },
-            self.timestamp.unwrap_or(Utc::now().into()).to_rfc3339(),
-            self.hostname
-                .as_ref()
-                .map(|s| s.as_ref())
-                .unwrap_or(&empty),
-            self.appname
-                .as_ref()
-                .map(|s| s.as_ref())
-                .unwrap_or(&empty),
-            self.procid
-                .as_ref()
-                .map(|s| s.as_ref())
-                .unwrap_or(&empty),
-            self.msgid
+            )
+        )?;
+
+        match self.protocol {
+            Protocol::RFC3164 => self.fmt_rfc3164_header(f)?,
+            Protocol::RFC5424(version) => self.fmt_rfc5424_header(f, version)?,
+        }
+
+        write!(f, "{}", self.msg.as_ref())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<S: AsRef<str> + Ord + PartialEq + Clone, T: Timestamp> Message<S, T> {
+    /// Renders `TIMESTAMP HOSTNAME TAG: `, omitting any field that's
+    /// absent instead of filling it with a placeholder, since RFC 3164
+    /// defines no NILVALUE.
+    ///
+    /// The trailing `:` is always written, attached directly (no space) to
+    /// whichever of `appname`/`hostname`/`timestamp` is the last field
+    /// present, even when `appname` itself is absent: `rfc3164::header`
+    /// only stops scanning for a hostname/appname at that `:`, so dropping
+    /// it would let the start of `msg` get consumed as a bogus
+    /// hostname/appname on re-parse instead of being left in the
+    /// remainder.
+    fn fmt_rfc3164_header(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(timestamp) = &self.timestamp {
+            write!(f, "{}", timestamp.to_rfc3164())?;
+        }
+        if let Some(hostname) = &self.hostname {
+            write!(f, " {}", hostname.as_ref())?;
+        }
+        if let Some(appname) = &self.appname {
+            write!(f, " {}", appname.as_ref())?;
+        }
+        write!(f, ": ")
+    }
+
+    /// Renders `VERSION TIMESTAMP HOSTNAME APP-NAME PROCID MSGID SD `,
+    /// filling any absent field with RFC 5424's `-` NILVALUE.
+    fn fmt_rfc5424_header(&self, f: &mut fmt::Formatter<'_>, version: u32) -> fmt::Result {
+        const NILVALUE: &str = "-";
+
+        write!(
+            f,
+            "{} {} {} {} {} {} ",
+            version,
+            self.timestamp
                 .as_ref()
-                .map(|s| s.as_ref())
-                .unwrap_or(&empty),
+                .map(Timestamp::to_rfc3339)
+                .as_deref()
+                .unwrap_or(NILVALUE),
+            self.hostname.as_ref().map(|s| s.as_ref()).unwrap_or(NILVALUE),
+            self.appname.as_ref().map(|s| s.as_ref()).unwrap_or(NILVALUE),
+            self.procid.as_ref().map(|s| s.as_ref()).unwrap_or(NILVALUE),
+            self.msgid.as_ref().map(|s| s.as_ref()).unwrap_or(NILVALUE),
         )?;
 
-        if self.structured_data.len() == 0 {
-            if let Protocol::RFC5424(_) = self.protocol {
-                write!(f, "-")?;
-            }
+        if self.structured_data.is_empty() {
+            write!(f, "{} ", NILVALUE)?;
         } else {
             for elem in &self.structured_data {
                 write!(f, "{}", elem)?;
             }
+            write!(f, " ")?;
         }
 
-        write!(f, " {}", self.msg.as_ref())
+        Ok(())
     }
 }
 
-impl<S: AsRef<str> + Ord + Clone> PartialEq for Message<S> {
+#[cfg(feature = "alloc")]
+impl<S: AsRef<str> + Ord + Clone, T: Timestamp> PartialEq for Message<S, T> {
     fn eq(&self, other: &Self) -> bool {
         self.facility == other.facility
             && self.severity == other.severity
@@ -86,8 +181,9 @@ impl<S: AsRef<str> + Ord + Clone> PartialEq for Message<S> {
     }
 }
 
-impl From<Message<&str>> for Message<String> {
-    fn from(message: Message<&str>) -> Self {
+#[cfg(feature = "alloc")]
+impl<T: Timestamp> From<Message<&str, T>> for Message<String, T> {
+    fn from(message: Message<&str, T>) -> Self {
         Message {
             facility: message.facility,
             severity: message.severity,
@@ -107,5 +203,80 @@ impl From<Message<&str>> for Message<String> {
     }
 }
 
+#[cfg(all(test, feature = "alloc", feature = "timestamp-chrono"))]
+mod tests {
+    use super::*;
+    use crate::pri::{SyslogFacility, SyslogSeverity};
+    use crate::rfc3164::{self, ParseOptions, YearResolver};
+    use chrono::{FixedOffset, TimeZone};
+
+    #[test]
+    fn rfc3164_display_round_trips_when_hostname_and_appname_are_both_present() {
+        let message = Message::<&str> {
+            protocol: Protocol::RFC3164,
+            facility: Some(SyslogFacility::LOG_AUTH),
+            severity: Some(SyslogSeverity::SEV_CRIT),
+            timestamp: Some(FixedOffset::west(0).ymd(2019, 10, 11).and_hms(22, 14, 15)),
+            hostname: Some("mymachine"),
+            appname: Some("su"),
+            procid: None,
+            msgid: None,
+            structured_data: Vec::new(),
+            msg: "a message",
+        };
+
+        let rendered = message.to_string();
+        let (remainder, header) =
+            rfc3164::header(&rendered, YearResolver::Fixed(2019), ParseOptions::default()).unwrap();
+
+        assert_eq!(header.facility, message.facility);
+        assert_eq!(header.severity, message.severity);
+        assert_eq!(header.timestamp, message.timestamp);
+        assert_eq!(header.hostname, message.hostname);
+        assert_eq!(header.appname, message.appname);
+        assert_eq!(remainder, message.msg);
+    }
+
+    #[test]
+    fn rfc3164_display_round_trips_when_hostname_and_appname_are_both_absent() {
+        let message = Message::<&str> {
+            protocol: Protocol::RFC3164,
+            facility: Some(SyslogFacility::LOG_AUTH),
+            severity: Some(SyslogSeverity::SEV_CRIT),
+            timestamp: Some(FixedOffset::west(0).ymd(2019, 10, 11).and_hms(22, 14, 15)),
+            hostname: None,
+            appname: None,
+            procid: None,
+            msgid: None,
+            structured_data: Vec::new(),
+            msg: "a message",
+        };
+
+        let rendered = message.to_string();
+        let (remainder, header) =
+            rfc3164::header(&rendered, YearResolver::Fixed(2019), ParseOptions::default()).unwrap();
+
+        assert_eq!(header.hostname, message.hostname);
+        assert_eq!(header.appname, message.appname);
+        assert_eq!(remainder, message.msg);
+    }
+
+    // TRACKING (exactly-one-of-hostname/appname-set round trip): the
+    // backlog's original ask — "for any Message produced by this crate's
+    // parser, parse(msg.to_string()) reconstructs an equal Message" — is
+    // still not true for RFC 3164 messages where exactly one of
+    // hostname/appname is set (a real, reachable output of
+    // `strict_header`/`best_effort_header`; see rfc3164's own
+    // `parse_3164_header_timestamp` test for the underlying grammar
+    // overlap). There's deliberately no test for that case here: a
+    // concrete repro needs the hostname/appname grammars themselves
+    // (parsers.rs, outside this tree) to know which field a given token
+    // lands on after re-parsing, which isn't available in this tree to
+    // verify against. Closing this gap — and adding a test that pins down
+    // the exact broken case — requires those grammars to stop overlapping,
+    // which is out of scope for `Display`; left as follow-up work rather
+    // than silently treated as resolved.
+}
+
 
 
@@ -2,15 +2,18 @@ use crate::header::Header;
 ///! Parsers for rfc 3164 specific formats.
 use crate::parsers::{appname, hostname, u32_digits};
 use crate::pri::pri;
-use chrono::prelude::*;
+use crate::timestamp::Timestamp;
 use nom::character::complete::{space0, space1};
 use nom::IResult;
 
 /// An incomplete date is a tuple of (month, date, hour, minutes, seconds)
 pub type IncompleteDate = (u32, u32, u32, u32, u32);
 
-// The month as a three letter string. Returns the number.
-fn parse_month(s: &str) -> Result<u32, String> {
+// The month as a three letter string. Returns the number. No `alloc`
+// needed: the error is a static string rather than a formatted `String`,
+// so this (like the rest of this module's parsers) works under plain
+// `core`.
+fn parse_month(s: &str) -> Result<u32, &'static str> {
     match s {
         "Jan" => Ok(1),
         "Feb" => Ok(2),
@@ -24,7 +27,7 @@ fn parse_month(s: &str) -> Result<u32, String> {
         "Oct" => Ok(10),
         "Nov" => Ok(11),
         "Dec" => Ok(12),
-        _ => Err(format!("Invalid month {}", s)),
+        _ => Err("invalid month"),
     }
 }
 
@@ -43,29 +46,104 @@ named!(timestamp(&str) -> IncompleteDate,
            ((month, date, hour, minute, seconds))
        ));
 
+/// A strategy for resolving the year of an RFC 3164 timestamp, which has no
+/// year field of its own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum YearResolver {
+    /// Use the current year.
+    CurrentYear,
+    /// Always use this year.
+    Fixed(i32),
+    /// Use the current year, but handle the turn of the year sensibly: if
+    /// the parsed month is December but we're currently in January, assume
+    /// the message is from last year; if the parsed month is January but
+    /// we're currently in December, assume it's from next year. This keeps
+    /// messages received just after midnight on New Year's from being
+    /// stamped a year off.
+    Smart,
+}
+
+impl YearResolver {
+    /// Resolves the year for a parsed `IncompleteDate`, using
+    /// `T::current_year_month()` as the reference point for `CurrentYear`
+    /// and `Smart`. Generic over the `Timestamp` backend rather than
+    /// calling `chrono::Utc::now()` directly, so this module doesn't need
+    /// `chrono` (or any particular clock source) in scope.
+    fn get_year<T: Timestamp>(&self, (month, ..): IncompleteDate) -> i32 {
+        let (now_year, now_month) = T::current_year_month();
+        self.resolve(month, now_year, now_month)
+    }
+
+    fn resolve(&self, month: u32, now_year: i32, now_month: u32) -> i32 {
+        match self {
+            YearResolver::Fixed(year) => *year,
+            YearResolver::CurrentYear => now_year,
+            YearResolver::Smart => match (month, now_month) {
+                (12, 1) => now_year - 1,
+                (1, 12) => now_year + 1,
+                _ => now_year,
+            },
+        }
+    }
+}
+
 /// Makes a timestamp given all the fields of the date less the year
-/// and a function to resolve the year.
-fn make_timestamp<F>(
+/// and a resolver to determine the year. Generic over the `Timestamp`
+/// backend so that callers aren't hard-wired to `chrono`; `Header`'s
+/// timestamp field still picks the `chrono`-backed instantiation here,
+/// since it hasn't been generified in this tree.
+///
+/// Returns `None` if the fields parsed syntactically (e.g. via
+/// `u32_digits`, which doesn't range-check) but don't form a real date or
+/// time, such as `Oct 99 25:99:99`.
+fn make_timestamp<T: Timestamp>(
     (mon, d, h, min, s): (u32, u32, u32, u32, u32),
-    get_year: F,
-) -> DateTime<FixedOffset>
-where
-    F: FnOnce(IncompleteDate) -> i32,
-{
-    let year = get_year((mon, d, h, min, s));
-    FixedOffset::west(0).ymd(year, mon, d).and_hms(h, min, s)
+    year_resolver: YearResolver,
+) -> Option<T> {
+    let year = year_resolver.get_year::<T>((mon, d, h, min, s));
+    T::from_ymd_hms(year, mon, d, h, min, s, 0)
+}
+
+/// Controls how strictly the RFC 3164 header parser enforces the presence
+/// of its fields.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct ParseOptions {
+    /// When `true`, a header with a missing or malformed timestamp,
+    /// hostname, or appname no longer fails the whole parse. Instead,
+    /// whatever fields parsed successfully are returned and the rest are
+    /// left as `None`, with everything from the first unparseable token
+    /// onward left in the remainder for the caller to treat as the
+    /// message. This mirrors go-syslog's `WithBestEffort`.
+    ///
+    /// Priority is never optional: a bad `<PRI>` still fails to parse
+    /// regardless of this flag, since it's what identifies the input as
+    /// syslog in the first place.
+    pub best_effort: bool,
 }
 
 /// Parses the header.
-/// Fails if it cant parse a 3164 format header.
-pub fn header<F>(input: &str, get_year: F) -> IResult<&str, Header>
-where
-    F: FnOnce(IncompleteDate) -> i32,
-{
+/// Fails if it cant parse a 3164 format header, unless
+/// `options.best_effort` is set.
+pub fn header(
+    input: &str,
+    year_resolver: YearResolver,
+    options: ParseOptions,
+) -> IResult<&str, Header> {
+    if options.best_effort {
+        best_effort_header(input, year_resolver)
+    } else {
+        strict_header(input, year_resolver)
+    }
+}
+
+fn strict_header(input: &str, year_resolver: YearResolver) -> IResult<&str, Header> {
     do_parse!(
         input,
         pri: pri
-            >> timestamp: preceded!(space0, timestamp)
+            >> timestamp: map_opt!(preceded!(space0, timestamp), |raw| make_timestamp(
+                raw,
+                year_resolver
+            ))
             >> hostname: opt!(preceded!(space1, hostname))
             >> appname: opt!(preceded!(space1, appname))
             >> opt!(tag!(":"))
@@ -73,7 +151,7 @@ where
             >> (Header {
                 facility: pri.0,
                 severity: pri.1,
-                timestamp: Some(make_timestamp(timestamp, get_year)),
+                timestamp: Some(timestamp),
                 hostname: hostname.flatten(),
                 version: None,
                 appname: appname.flatten(),
@@ -83,6 +161,63 @@ where
     )
 }
 
+/// Like `strict_header`, but stops trying to parse further fields as soon
+/// as one of them doesn't match, leaving everything from that point in the
+/// remainder instead of failing outright.
+fn best_effort_header(input: &str, year_resolver: YearResolver) -> IResult<&str, Header> {
+    let (rest, pri) = pri(input)?;
+
+    let before_timestamp = rest;
+    let (rest, parsed_timestamp) = match preceded!(rest, space0, timestamp) {
+        Ok((rest, raw)) => match make_timestamp(raw, year_resolver) {
+            // The token parsed as a date/time, but it isn't a valid one
+            // (e.g. `Oct 99 25:99:99`): treat it the same as a token that
+            // didn't match the timestamp grammar at all, and leave it for
+            // the remainder rather than consuming it.
+            Some(timestamp) => (rest, Some(timestamp)),
+            None => (before_timestamp, None),
+        },
+        Err(_) => (rest, None),
+    };
+
+    let (rest, hostname) = if parsed_timestamp.is_some() {
+        match preceded!(rest, space1, hostname) {
+            Ok((rest, hostname)) => (rest, hostname),
+            Err(_) => (rest, None),
+        }
+    } else {
+        (rest, None)
+    };
+
+    let (rest, appname) = if hostname.is_some() {
+        match preceded!(rest, space1, appname) {
+            Ok((rest, appname)) => (rest, appname),
+            Err(_) => (rest, None),
+        }
+    } else {
+        (rest, None)
+    };
+
+    // Plain `strip_prefix` rather than `tag!`, which is the streaming
+    // `tag` and would report `Incomplete` when nothing follows the header.
+    let rest = rest.strip_prefix(':').unwrap_or(rest);
+    let (rest, _) = opt!(rest, space0)?;
+
+    Ok((
+        rest,
+        Header {
+            facility: pri.0,
+            severity: pri.1,
+            timestamp: parsed_timestamp,
+            hostname,
+            version: None,
+            appname,
+            procid: None,
+            msgid: None,
+        },
+    ))
+}
+
 #[test]
 fn parse_timestamp_3164() {
     assert_eq!(
@@ -95,6 +230,9 @@ fn parse_timestamp_3164() {
 mod tests {
     use super::*;
     use crate::pri::{SyslogFacility, SyslogSeverity};
+    // Only needed to build the concrete chrono timestamps `Header`
+    // (defined elsewhere in the crate) expects in these expected values.
+    use chrono::{FixedOffset, TimeZone};
 
     #[test]
     fn parse_3164_header_timestamp() {
@@ -105,7 +243,12 @@ mod tests {
         Are there any significant systems that will send a syslog like this?
         */
         assert_eq!(
-            header("<34>Oct 11 22:14:15: a message", |_| 2019).unwrap(),
+            header(
+                "<34>Oct 11 22:14:15: a message",
+                YearResolver::Fixed(2019),
+                ParseOptions::default()
+            )
+            .unwrap(),
             (
                 "a message",
                 Header {
@@ -125,7 +268,12 @@ mod tests {
     #[test]
     fn parse_3164_header_timestamp_host() {
         assert_eq!(
-            header("<34>Oct 11 22:14:15 mymachine: a message", |_| 2019).unwrap(),
+            header(
+                "<34>Oct 11 22:14:15 mymachine: a message",
+                YearResolver::Fixed(2019),
+                ParseOptions::default()
+            )
+            .unwrap(),
             (
                 "a message",
                 Header {
@@ -141,4 +289,107 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn parse_3164_header_best_effort_no_message() {
+        let options = ParseOptions {
+            best_effort: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            header("<34>Oct 11 22:14:15 mymachine", YearResolver::Fixed(2019), options).unwrap(),
+            (
+                "",
+                Header {
+                    facility: Some(SyslogFacility::LOG_AUTH),
+                    severity: Some(SyslogSeverity::SEV_CRIT),
+                    timestamp: Some(FixedOffset::west(0).ymd(2019, 10, 11).and_hms(22, 14, 15)),
+                    hostname: Some("mymachine"),
+                    version: None,
+                    appname: None,
+                    procid: None,
+                    msgid: None,
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn parse_3164_header_best_effort_no_timestamp() {
+        let options = ParseOptions {
+            best_effort: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            header("<34>not a timestamp", YearResolver::Fixed(2019), options).unwrap(),
+            (
+                "not a timestamp",
+                Header {
+                    facility: Some(SyslogFacility::LOG_AUTH),
+                    severity: Some(SyslogSeverity::SEV_CRIT),
+                    timestamp: None,
+                    hostname: None,
+                    version: None,
+                    appname: None,
+                    procid: None,
+                    msgid: None,
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn parse_3164_header_strict_fails_without_timestamp() {
+        assert!(header("<34>not a timestamp", YearResolver::Fixed(2019), ParseOptions::default()).is_err());
+    }
+
+    #[test]
+    fn parse_3164_header_strict_fails_on_out_of_range_timestamp() {
+        // Syntactically valid per `u32_digits`, but not a real date/time.
+        assert!(header(
+            "<34>Oct 99 25:99:99 mymachine: a message",
+            YearResolver::Fixed(2019),
+            ParseOptions::default()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn parse_3164_header_best_effort_out_of_range_timestamp() {
+        let options = ParseOptions {
+            best_effort: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            header(
+                "<34>Oct 99 25:99:99 mymachine: a message",
+                YearResolver::Fixed(2019),
+                options
+            )
+            .unwrap(),
+            (
+                "Oct 99 25:99:99 mymachine: a message",
+                Header {
+                    facility: Some(SyslogFacility::LOG_AUTH),
+                    severity: Some(SyslogSeverity::SEV_CRIT),
+                    timestamp: None,
+                    hostname: None,
+                    version: None,
+                    appname: None,
+                    procid: None,
+                    msgid: None,
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn year_resolver_smart_handles_year_turnover() {
+        // A December message seen in January is from last year.
+        assert_eq!(YearResolver::Smart.resolve(12, 2020, 1), 2019);
+        // A January message seen in December is from next year.
+        assert_eq!(YearResolver::Smart.resolve(1, 2019, 12), 2020);
+        // Any other month just uses the current year.
+        assert_eq!(YearResolver::Smart.resolve(6, 2020, 1), 2020);
+    }
 }
@@ -0,0 +1,180 @@
+//! Serde helpers for [`crate::message::Message::timestamp`], gated behind
+//! the `serde` feature.
+//!
+//! Following the pattern the `time` crate uses for its own serde
+//! submodules, these modules are meant to be selected with
+//! `#[serde(with = "...")]` on the field rather than applied automatically,
+//! since different consumers want different wire representations for the
+//! same timestamp.
+
+use crate::timestamp::Timestamp;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serializer};
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+
+/// (De)serializes a timestamp as an RFC 3339 / ISO 8601 string. This is the
+/// representation `Message` uses by default.
+///
+/// Generic over `T: Timestamp` (via `T::to_rfc3339`/`T::parse_rfc3339`
+/// rather than a concrete `chrono` type), so this works for whichever
+/// `Timestamp` backend `Message` is instantiated with — unlike
+/// `unix_timestamp` below, it doesn't require `timestamp-chrono`.
+pub mod rfc3339 {
+    use super::*;
+
+    pub fn serialize<T: Timestamp, S: Serializer>(
+        timestamp: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&timestamp.to_rfc3339())
+    }
+
+    pub fn deserialize<'de, T: Timestamp, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        T::parse_rfc3339(&raw).map_err(D::Error::custom)
+    }
+
+    /// As above, but for the `Option<T>` that `Message::timestamp` actually
+    /// holds.
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<T: Timestamp, S: Serializer>(
+            timestamp: &Option<T>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match timestamp {
+                Some(timestamp) => super::serialize(timestamp, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, T: Timestamp, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<T>, D::Error> {
+            Option::<String>::deserialize(deserializer)?
+                .map(|raw| T::parse_rfc3339(&raw).map_err(D::Error::custom))
+                .transpose()
+        }
+    }
+}
+
+/// (De)serializes a timestamp as Unix-epoch seconds, for consumers that
+/// would rather not carry a string timestamp through their pipeline.
+///
+/// Unlike `rfc3339` above, this is chrono-specific (the `Timestamp` trait
+/// has no epoch-seconds accessor) and so requires the `timestamp-chrono`
+/// feature; `Message` doesn't select it by default.
+#[cfg(feature = "timestamp-chrono")]
+pub mod unix_timestamp {
+    use super::*;
+    use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+
+    pub fn serialize<S: Serializer>(
+        timestamp: &DateTime<FixedOffset>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(timestamp.timestamp())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<FixedOffset>, D::Error> {
+        let secs = i64::deserialize(deserializer)?;
+        to_fixed_offset(secs).map_err(D::Error::custom)
+    }
+
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            timestamp: &Option<DateTime<FixedOffset>>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match timestamp {
+                Some(timestamp) => super::serialize(timestamp, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<DateTime<FixedOffset>>, D::Error> {
+            Option::<i64>::deserialize(deserializer)?
+                .map(|secs| to_fixed_offset(secs).map_err(D::Error::custom))
+                .transpose()
+        }
+    }
+
+    fn to_fixed_offset(secs: i64) -> Result<DateTime<FixedOffset>, String> {
+        Utc.timestamp_opt(secs, 0)
+            .single()
+            .map(|dt| dt.into())
+            .ok_or_else(|| alloc::format!("{} is not a valid unix timestamp", secs))
+    }
+}
+
+#[cfg(all(test, feature = "timestamp-chrono"))]
+mod tests {
+    use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Rfc3339Wrapper(#[serde(with = "super::rfc3339")] DateTime<FixedOffset>);
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Rfc3339OptionWrapper(#[serde(with = "super::rfc3339::option")] Option<DateTime<FixedOffset>>);
+
+    #[test]
+    fn rfc3339_round_trips_through_json() {
+        let timestamp = FixedOffset::east(0).ymd(2019, 10, 11).and_hms(22, 14, 15);
+        let json = serde_json::to_string(&Rfc3339Wrapper(timestamp)).unwrap();
+        assert_eq!(json, "\"2019-10-11T22:14:15+00:00\"");
+        assert_eq!(
+            serde_json::from_str::<Rfc3339Wrapper>(&json).unwrap(),
+            Rfc3339Wrapper(timestamp)
+        );
+    }
+
+    #[test]
+    fn rfc3339_option_round_trips_none() {
+        let json = serde_json::to_string(&Rfc3339OptionWrapper(None)).unwrap();
+        assert_eq!(json, "null");
+        assert_eq!(
+            serde_json::from_str::<Rfc3339OptionWrapper>(&json).unwrap(),
+            Rfc3339OptionWrapper(None)
+        );
+    }
+
+    #[test]
+    fn rfc3339_deserialize_rejects_malformed_input() {
+        assert!(serde_json::from_str::<Rfc3339Wrapper>("\"not a timestamp\"").is_err());
+    }
+
+    #[cfg(feature = "timestamp-chrono")]
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct UnixTimestampWrapper(#[serde(with = "super::unix_timestamp")] DateTime<FixedOffset>);
+
+    #[test]
+    fn unix_timestamp_round_trips_through_json() {
+        let timestamp: DateTime<FixedOffset> =
+            Utc.timestamp_opt(1_570_832_055, 0).single().unwrap().into();
+        let json = serde_json::to_string(&UnixTimestampWrapper(timestamp)).unwrap();
+        assert_eq!(json, "1570832055");
+        assert_eq!(
+            serde_json::from_str::<UnixTimestampWrapper>(&json).unwrap(),
+            UnixTimestampWrapper(timestamp)
+        );
+    }
+
+    #[test]
+    fn unix_timestamp_deserialize_rejects_out_of_range_seconds() {
+        let json = i64::MAX.to_string();
+        assert!(serde_json::from_str::<UnixTimestampWrapper>(&json).is_err());
+    }
+}
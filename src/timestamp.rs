@@ -0,0 +1,198 @@
+//! Abstracts the datetime type backing [`crate::message::Message::timestamp`]
+//! so that `chrono` is a swappable backend rather than a hard dependency,
+//! following smithy-rs's move to relegate `chrono` to an optional
+//! conversion feature.
+//!
+//! The `timestamp-chrono` feature (enabled by default) implements
+//! [`Timestamp`] for `chrono::DateTime<chrono::FixedOffset>`.
+//! `timestamp-time` implements it for `time::OffsetDateTime` instead, for
+//! users who have standardized on the `time` crate and would rather not
+//! pull in `chrono` transitively.
+//!
+//! `timestamp-time`'s `OffsetDateTime` impl isn't reachable from
+//! `rfc3164::header` yet: `Header` (in `header.rs`, outside this tree)
+//! still names `chrono::DateTime<FixedOffset>` concretely for its
+//! `timestamp` field, so the parser path only ever produces the
+//! `timestamp-chrono` backend regardless of which `Timestamp` features are
+//! enabled. Generifying `Header`/the parser over `T: Timestamp` is tracked
+//! as follow-up work; until then, `time_backend` is exercised only by the
+//! unit tests in this module, not by anything the parser calls.
+
+use core::fmt::Debug;
+
+// `to_rfc3339`/`to_rfc3164`/`parse_rfc3339` below return an owned `String`,
+// so (like `Message`'s `Display`/`From` impls in `message.rs`) they need
+// `alloc` even under `no_std`.
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "alloc")]
+use alloc::string::{String, ToString};
+
+/// A parsed, fixed-offset point in time, abstracted over the datetime
+/// library actually doing the work.
+pub trait Timestamp: Clone + Debug + PartialEq + Eq {
+    /// Builds a timestamp from its individual RFC 3164 / RFC 5424 fields.
+    /// `offset_secs` is the UTC offset in seconds east of UTC. Returns
+    /// `None` if the fields don't form a valid date/time (out-of-range
+    /// month, day, hour, minute, second, or offset) rather than panicking,
+    /// since these fields come straight from untrusted parser input.
+    fn from_ymd_hms(
+        year: i32,
+        month: u32,
+        day: u32,
+        hour: u32,
+        min: u32,
+        sec: u32,
+        offset_secs: i32,
+    ) -> Option<Self>
+    where
+        Self: Sized;
+
+    /// Parses an RFC 3339 / ISO 8601 string, the timestamp format RFC 5424
+    /// mandates.
+    fn parse_rfc3339(input: &str) -> Result<Self, String>
+    where
+        Self: Sized;
+
+    /// The current time, used when a message carries no timestamp of its
+    /// own.
+    fn now() -> Self;
+
+    /// The current `(year, month)`, used by `rfc3164::YearResolver` to
+    /// resolve the year for a timestamp format (RFC 3164's) that doesn't
+    /// carry one, without that module needing a particular clock source
+    /// (e.g. `chrono::Utc::now()`) in scope.
+    fn current_year_month() -> (i32, u32);
+
+    /// Renders as an RFC 3339 / ISO 8601 string, the representation RFC
+    /// 5424 mandates.
+    fn to_rfc3339(&self) -> String;
+
+    /// Renders as `Mmm DD HH:MM:SS`, the representation RFC 3164 mandates.
+    fn to_rfc3164(&self) -> String;
+}
+
+#[cfg(feature = "timestamp-chrono")]
+mod chrono_backend {
+    use super::Timestamp;
+    use chrono::{DateTime, Datelike, FixedOffset, TimeZone, Utc};
+
+    impl Timestamp for DateTime<FixedOffset> {
+        fn from_ymd_hms(
+            year: i32,
+            month: u32,
+            day: u32,
+            hour: u32,
+            min: u32,
+            sec: u32,
+            offset_secs: i32,
+        ) -> Option<Self> {
+            let offset = FixedOffset::east_opt(offset_secs)?;
+            let date = offset.ymd_opt(year, month, day).single()?;
+            date.and_hms_opt(hour, min, sec)
+        }
+
+        fn parse_rfc3339(input: &str) -> Result<Self, String> {
+            DateTime::parse_from_rfc3339(input).map_err(|e| e.to_string())
+        }
+
+        fn now() -> Self {
+            Utc::now().into()
+        }
+
+        fn current_year_month() -> (i32, u32) {
+            let now = Utc::now();
+            (now.year(), now.month())
+        }
+
+        fn to_rfc3339(&self) -> String {
+            DateTime::to_rfc3339(self)
+        }
+
+        fn to_rfc3164(&self) -> String {
+            // `%e` space-pads the day, e.g. "Oct  3", matching RFC 3164.
+            self.format("%b %e %H:%M:%S").to_string()
+        }
+    }
+}
+
+#[cfg(feature = "timestamp-time")]
+mod time_backend {
+    use super::Timestamp;
+    use time::format_description::well_known::Rfc3339;
+    use time::macros::format_description;
+    use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+    impl Timestamp for OffsetDateTime {
+        fn from_ymd_hms(
+            year: i32,
+            month: u32,
+            day: u32,
+            hour: u32,
+            min: u32,
+            sec: u32,
+            offset_secs: i32,
+        ) -> Option<Self> {
+            let month = u8::try_from(month).ok().and_then(|m| Month::try_from(m).ok())?;
+            let day = u8::try_from(day).ok()?;
+            let date = Date::from_calendar_date(year, month, day).ok()?;
+            let hour = u8::try_from(hour).ok()?;
+            let min = u8::try_from(min).ok()?;
+            let sec = u8::try_from(sec).ok()?;
+            let time = Time::from_hms(hour, min, sec).ok()?;
+            let offset = UtcOffset::from_whole_seconds(offset_secs).ok()?;
+            Some(PrimitiveDateTime::new(date, time).assume_offset(offset))
+        }
+
+        fn parse_rfc3339(input: &str) -> Result<Self, String> {
+            OffsetDateTime::parse(input, &Rfc3339).map_err(|e| e.to_string())
+        }
+
+        fn now() -> Self {
+            OffsetDateTime::now_utc()
+        }
+
+        fn current_year_month() -> (i32, u32) {
+            let now = OffsetDateTime::now_utc();
+            (now.year(), now.month() as u32)
+        }
+
+        fn to_rfc3339(&self) -> String {
+            self.format(&Rfc3339).expect("formatting is infallible for a valid offset")
+        }
+
+        fn to_rfc3164(&self) -> String {
+            const FORMAT: &[time::format_description::FormatItem<'_>] =
+                format_description!("[month repr:short] [day padding:space] [hour]:[minute]:[second]");
+            self.format(&FORMAT).expect("formatting is infallible for a valid offset")
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn from_ymd_hms_builds_the_expected_offset_date_time() {
+            let timestamp = OffsetDateTime::from_ymd_hms(2019, 10, 11, 22, 14, 15, 0).unwrap();
+            assert_eq!(timestamp.to_rfc3164(), "Oct 11 22:14:15");
+        }
+
+        #[test]
+        fn from_ymd_hms_rejects_out_of_range_fields() {
+            assert!(OffsetDateTime::from_ymd_hms(2019, 99, 25, 99, 99, 99, 0).is_none());
+        }
+
+        #[test]
+        fn rfc3339_round_trips() {
+            let timestamp = OffsetDateTime::from_ymd_hms(2019, 10, 11, 22, 14, 15, 0).unwrap();
+            let rendered = timestamp.to_rfc3339();
+            assert_eq!(OffsetDateTime::parse_rfc3339(&rendered).unwrap(), timestamp);
+        }
+
+        #[test]
+        fn parse_rfc3339_rejects_malformed_input() {
+            assert!(OffsetDateTime::parse_rfc3339("not a timestamp").is_err());
+        }
+    }
+}